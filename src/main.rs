@@ -1,14 +1,367 @@
+use std::collections::{BTreeMap, HashMap};
 use std::process::Command;
-use std::io::{stdout, Stdout, Write};
+use std::io::{stdin, stdout, IsTerminal, Stdout, Write};
+use std::path::PathBuf;
 use crossterm::{
     cursor::{Hide, MoveTo, Show, position},
-    event::{read, Event, KeyCode, KeyEvent},
+    event::{read, Event, KeyCode, KeyEvent, KeyModifiers},
     execute,
     style::Print,
-    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
+    terminal::{disable_raw_mode, enable_raw_mode, size, Clear, ClearType},
 };
 
+/// RAII guard that restores the terminal to a sane state on every exit path.
+///
+/// Constructing one takes ownership of a `Stdout` handle; its `Drop` impl shows
+/// the cursor and disables raw mode, so whether `select_option` returns
+/// normally, returns early, or unwinds through a panic, the user's shell is
+/// never left with echo off and the cursor hidden.
+struct TermGuard(Stdout);
+
+impl Drop for TermGuard {
+    fn drop(&mut self) {
+        let _ = execute!(self.0, Show);
+        let _ = disable_raw_mode();
+    }
+}
+
+/// A single refresh rate advertised for a mode, carrying the `*`/`+` flags
+/// `xrandr --query` prints next to it.
+struct Rate {
+    /// The rate as xrandr prints it, e.g. `"60.00"`.
+    value: String,
+    /// Set when xrandr marked this rate with `*` (the active mode).
+    current: bool,
+    /// Set when xrandr marked this rate with `+` (the preferred mode).
+    preferred: bool,
+}
+
+/// One `WxH` mode of an output together with its available refresh rates.
+struct Mode {
+    width: u32,
+    height: u32,
+    rates: Vec<Rate>,
+}
+
+impl Mode {
+    /// The `WxH` resolution string used as a menu label and `--mode` argument.
+    fn resolution(&self) -> String {
+        format!("{}x{}", self.width, self.height)
+    }
+}
+
+/// Parse the indented mode lines beneath each output header in `xrandr --query`
+/// output, returning the modes keyed by output name. Lines that don't belong to
+/// a connected output (or aren't mode lines) are ignored.
+fn parse_modes(xrandr_str: &str) -> HashMap<String, Vec<Mode>> {
+    let mut modes: HashMap<String, Vec<Mode>> = HashMap::new();
+    let mut current: Option<String> = None;
+
+    for line in xrandr_str.lines() {
+        // Output header lines start in the first column; mode lines are indented.
+        let indented = line.starts_with(char::is_whitespace);
+        if !indented {
+            current = if line.contains(" connected") {
+                line.split_whitespace().next().map(|s| s.to_string())
+            } else {
+                None
+            };
+            continue;
+        }
+
+        let Some(output) = current.as_ref() else {
+            continue;
+        };
+
+        let mut tokens = line.split_whitespace();
+        let Some(res) = tokens.next() else { continue };
+        let Some((w, h)) = res.split_once('x') else { continue };
+        let (Ok(width), Ok(height)) = (w.parse::<u32>(), h.parse::<u32>()) else {
+            continue;
+        };
+
+        let rates = tokens
+            .map(|tok| Rate {
+                value: tok.trim_end_matches(['*', '+']).to_string(),
+                current: tok.contains('*'),
+                preferred: tok.contains('+'),
+            })
+            .collect();
+
+        modes
+            .entry(output.clone())
+            .or_default()
+            .push(Mode { width, height, rates });
+    }
+
+    modes
+}
+
+/// The resolution and rate xrandr flagged as current (`*`) for an output, used
+/// to record the primary's existing state when saving a profile.
+fn current_mode_rate(modes: &[Mode]) -> (Option<String>, Option<String>) {
+    for mode in modes {
+        for rate in &mode.rates {
+            if rate.current {
+                return (Some(mode.resolution()), Some(rate.value.clone()));
+            }
+        }
+    }
+    (None, None)
+}
+
+/// A resolution advertised by both outputs, for mirroring/`--same-as`.
+///
+/// Prefers a shared resolution the mirrored output marks preferred (`+`), otherwise
+/// the first one they have in common (xrandr lists highest first). Returns
+/// `None` when the mode tables don't overlap, in which case the caller omits
+/// `--mode` and lets xrandr choose.
+fn common_mode(primary_modes: &[Mode], output_modes: &[Mode]) -> Option<String> {
+    let shared: Vec<&Mode> = output_modes
+        .iter()
+        .filter(|m| {
+            primary_modes
+                .iter()
+                .any(|p| p.width == m.width && p.height == m.height)
+        })
+        .collect();
+
+    if let Some(mode) = shared.iter().find(|m| m.rates.iter().any(|r| r.preferred)) {
+        return Some(mode.resolution());
+    }
+    shared.first().map(|m| m.resolution())
+}
+
+/// A single output's saved state within a layout profile.
+#[derive(Default)]
+struct OutputConfig {
+    name: String,
+    enabled: bool,
+    mode: Option<String>,
+    rate: Option<String>,
+    /// Positional relation to another output as `(relation, reference)`, e.g.
+    /// `("right-of", "eDP-1")`, or `("same-as", primary)` for a mirrored output.
+    /// `None` for a primary or a disabled output.
+    position: Option<(String, String)>,
+    primary: bool,
+}
+
+impl OutputConfig {
+    /// The `--output ...` argument fragment that reproduces this output's state,
+    /// suitable for concatenating into a single combined `xrandr` invocation.
+    fn to_args(&self) -> Vec<String> {
+        let mut args = vec!["--output".to_string(), self.name.clone()];
+        if !self.enabled {
+            args.push("--off".to_string());
+            return args;
+        }
+        match (&self.mode, &self.rate) {
+            (Some(mode), Some(rate)) => {
+                args.push("--mode".to_string());
+                args.push(mode.clone());
+                args.push("--rate".to_string());
+                args.push(rate.clone());
+            }
+            (Some(mode), None) => {
+                args.push("--mode".to_string());
+                args.push(mode.clone());
+            }
+            _ => args.push("--auto".to_string()),
+        }
+        if self.primary {
+            args.push("--primary".to_string());
+        }
+        if let Some((relation, reference)) = &self.position {
+            args.push(format!("--{}", relation));
+            args.push(reference.clone());
+        }
+        args
+    }
+}
+
+/// Path to the profiles file, honouring `XDG_CONFIG_HOME` and falling back to
+/// `~/.config/xrpick/profiles.toml`.
+fn config_path() -> PathBuf {
+    let base = std::env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| format!("{}/.config", std::env::var("HOME").unwrap_or_default()));
+    PathBuf::from(base).join("xrpick").join("profiles.toml")
+}
+
+/// Strip a surrounding pair of double quotes from a TOML value, if present.
+fn unquote(value: &str) -> String {
+    value
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(value)
+        .to_string()
+}
+
+/// Parse the profiles file into a map of profile name to its ordered outputs.
+///
+/// The file is an array-of-tables whose name is the profile, e.g. `[["dock"]]`,
+/// with one table per output; this keeps the format plain enough to read and
+/// write without pulling in a serialization dependency.
+fn parse_profiles(content: &str) -> BTreeMap<String, Vec<OutputConfig>> {
+    let mut profiles: BTreeMap<String, Vec<OutputConfig>> = BTreeMap::new();
+    let mut current_name: Option<String> = None;
+    let mut current: Option<OutputConfig> = None;
+
+    let flush = |name: &mut Option<String>, cfg: &mut Option<OutputConfig>,
+                 profiles: &mut BTreeMap<String, Vec<OutputConfig>>| {
+        if let (Some(name), Some(cfg)) = (name.clone(), cfg.take()) {
+            profiles.entry(name).or_default().push(cfg);
+        }
+    };
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(inner) = line.strip_prefix("[[").and_then(|s| s.strip_suffix("]]")) {
+            flush(&mut current_name, &mut current, &mut profiles);
+            current_name = Some(unquote(inner.trim()));
+            current = Some(OutputConfig::default());
+            continue;
+        }
+
+        if let (Some((key, value)), Some(cfg)) = (line.split_once('='), current.as_mut()) {
+            let key = key.trim();
+            let value = value.trim();
+            match key {
+                "name" => cfg.name = unquote(value),
+                "enabled" => cfg.enabled = value == "true",
+                "primary" => cfg.primary = value == "true",
+                "mode" => cfg.mode = Some(unquote(value)),
+                "rate" => cfg.rate = Some(unquote(value)),
+                "position" => {
+                    let raw = unquote(value);
+                    if let Some((relation, reference)) = raw.split_once(' ') {
+                        cfg.position = Some((relation.to_string(), reference.to_string()));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    flush(&mut current_name, &mut current, &mut profiles);
+    profiles
+}
+
+/// Render the profiles map back into the on-disk array-of-tables format.
+fn serialize_profiles(profiles: &BTreeMap<String, Vec<OutputConfig>>) -> String {
+    let mut out = String::new();
+    for (name, outputs) in profiles {
+        for cfg in outputs {
+            out.push_str(&format!("[[\"{}\"]]\n", name));
+            out.push_str(&format!("name = \"{}\"\n", cfg.name));
+            out.push_str(&format!("enabled = {}\n", cfg.enabled));
+            out.push_str(&format!("primary = {}\n", cfg.primary));
+            if let Some(mode) = &cfg.mode {
+                out.push_str(&format!("mode = \"{}\"\n", mode));
+            }
+            if let Some(rate) = &cfg.rate {
+                out.push_str(&format!("rate = \"{}\"\n", rate));
+            }
+            if let Some((relation, reference)) = &cfg.position {
+                out.push_str(&format!("position = \"{} {}\"\n", relation, reference));
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Load all saved profiles, returning an empty map when no file exists yet.
+fn load_profiles() -> BTreeMap<String, Vec<OutputConfig>> {
+    match std::fs::read_to_string(config_path()) {
+        Ok(content) => parse_profiles(&content),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+/// Persist `outputs` under `name`, replacing any existing profile of that name.
+fn save_profile(name: &str, outputs: Vec<OutputConfig>) {
+    let mut profiles = load_profiles();
+    profiles.insert(name.to_string(), outputs);
+
+    let path = config_path();
+    if let Some(parent) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            println!("Failed to create config directory: {}", e);
+            return;
+        }
+    }
+
+    match std::fs::write(&path, serialize_profiles(&profiles)) {
+        Ok(()) => println!("Saved profile '{}' to {}", name, path.display()),
+        Err(e) => println!("Failed to write profile: {}", e),
+    }
+}
+
+/// Print the names of all saved profiles (`xrpick --list`).
+fn list_profiles() {
+    let profiles = load_profiles();
+    if profiles.is_empty() {
+        println!("No saved profiles.");
+        return;
+    }
+    for name in profiles.keys() {
+        println!("{}", name);
+    }
+}
+
+/// Build a single combined `xrandr` invocation for every output in `name` and
+/// run it (`xrpick --apply NAME`).
+fn apply_profile(name: &str) {
+    let profiles = load_profiles();
+    let Some(outputs) = profiles.get(name) else {
+        println!("No profile named '{}'.", name);
+        return;
+    };
+
+    let args: Vec<String> = outputs.iter().flat_map(OutputConfig::to_args).collect();
+    println!("Running: xrandr {:?}", args);
+
+    let status = Command::new("xrandr")
+        .args(&args)
+        .status()
+        .expect("Failed to execute xrandr command");
+
+    if status.success() {
+        println!("Applied profile '{}'.", name);
+    } else {
+        println!("Failed to apply profile. Check xrandr output for errors.");
+    }
+}
+
 fn main() {
+    // Make sure a panic mid-menu can never leave the terminal in raw mode with
+    // the cursor hidden: restore it before the default hook prints the message.
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = execute!(stdout(), Show);
+        let _ = disable_raw_mode();
+        default_hook(info);
+    }));
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => run_interactive(None),
+        [flag] if flag == "--list" => list_profiles(),
+        [flag, name] if flag == "--apply" => apply_profile(name),
+        [flag, name] if flag == "--save" => run_interactive(Some(name.clone())),
+        _ => {
+            eprintln!("Usage: xrpick [--save NAME | --apply NAME | --list]");
+        }
+    }
+}
+
+fn run_interactive(save_as: Option<String>) {
     // Run xrandr --query to get display info
     let output = Command::new("xrandr")
         .arg("--query")
@@ -18,6 +371,9 @@ fn main() {
     // Convert the raw bytes to a string
     let xrandr_str = String::from_utf8_lossy(&output.stdout).to_string();
 
+    // Parse the mode/refresh-rate table beneath each output for later selection.
+    let modes = parse_modes(&xrandr_str);
+
     // Parse connected outputs and primary
     let mut connected_outputs: Vec<String> = Vec::new();
     let mut primary = String::new();
@@ -49,6 +405,20 @@ fn main() {
         return;
     }
 
+    // Record the arrangement as it's built so it can be saved as a profile.
+    // The primary is captured with its current mode/rate up front.
+    let (primary_mode, primary_rate) = current_mode_rate(
+        modes.get(&primary).map(Vec::as_slice).unwrap_or(&[]),
+    );
+    let mut arrangement: Vec<OutputConfig> = vec![OutputConfig {
+        name: primary.clone(),
+        enabled: true,
+        mode: primary_mode,
+        rate: primary_rate,
+        position: None,
+        primary: true,
+    }];
+
     // Interactive loop
     println!("Primary display: {}", primary);
     loop {
@@ -59,39 +429,113 @@ fn main() {
 
         // Select display
         let display_options: Vec<&str> = connected_outputs.iter().map(|s| s.as_str()).collect();
-        let selected_display = select_option("Select display to attach (arrow keys to move, enter to select, q to quit):", &display_options);
+        let selected_display = select_option("Select display to attach (type to filter, arrows to move, enter to select, Esc to quit):", &display_options);
 
         let Some(selected) = selected_display else {
             println!("Quitting.");
             break;
         };
 
-        // Select position
-        let position_options = vec!["left", "right", "above", "below"];
-        let selected_pos = select_option("Select position (arrow keys to move, enter to select, q to quit):", &position_options);
+        let output_modes = modes.get(&selected).map(Vec::as_slice).unwrap_or(&[]);
+        let primary_modes = modes.get(&primary).map(Vec::as_slice).unwrap_or(&[]);
 
-        let Some(pos) = selected_pos else {
+        // Choose what to do with this output.
+        let action_options = vec!["attach (position)", "mirror primary", "turn off", "make primary"];
+        let Some(action) = select_option(
+            "Select action (type to filter, arrows to move, enter to select, Esc to quit):",
+            &action_options,
+        ) else {
             println!("Quitting.");
             break;
         };
 
-        let pos_arg = match pos.as_str() {
-            "left" => "left-of",
-            "right" => "right-of",
-            "above" => "above",
-            "below" => "below",
+        // Build the xrandr argument fragment and the profile record for the
+        // chosen action.
+        let mut args = vec!["--output".to_string(), selected.clone()];
+        let record = match action.as_str() {
+            "attach (position)" => {
+                let position_options = vec!["left", "right", "above", "below"];
+                let Some(pos) = select_option("Select position (type to filter, arrows to move, enter to select, Esc to quit):", &position_options) else {
+                    println!("Quitting.");
+                    break;
+                };
+                let pos_arg = match pos.as_str() {
+                    "left" => "left-of",
+                    "right" => "right-of",
+                    "above" => "above",
+                    "below" => "below",
+                    _ => unreachable!(),
+                };
+
+                // Pick a resolution and refresh rate, falling back to `--auto`
+                // when none were parsed; quitting out of either menu quits.
+                let Some(mode_arg) = select_mode_args(output_modes) else {
+                    println!("Quitting.");
+                    break;
+                };
+                let (rec_mode, rec_rate) = match mode_arg.as_slice() {
+                    [m, mode, r, rate] if m == "--mode" && r == "--rate" => {
+                        (Some(mode.clone()), Some(rate.clone()))
+                    }
+                    _ => (None, None),
+                };
+
+                args.extend(mode_arg);
+                args.push(format!("--{}", pos_arg));
+                args.push(primary.clone());
+                OutputConfig {
+                    name: selected.clone(),
+                    enabled: true,
+                    mode: rec_mode,
+                    rate: rec_rate,
+                    position: Some((pos_arg.to_string(), primary.clone())),
+                    primary: false,
+                }
+            }
+            "mirror primary" => {
+                // Duplicate the primary using a resolution both panels support.
+                let common = common_mode(primary_modes, output_modes);
+                if let Some(mode) = &common {
+                    args.push("--mode".to_string());
+                    args.push(mode.clone());
+                }
+                args.push("--same-as".to_string());
+                args.push(primary.clone());
+                OutputConfig {
+                    name: selected.clone(),
+                    enabled: true,
+                    mode: common,
+                    rate: None,
+                    position: Some(("same-as".to_string(), primary.clone())),
+                    primary: false,
+                }
+            }
+            "turn off" => {
+                args.push("--off".to_string());
+                OutputConfig {
+                    name: selected.clone(),
+                    enabled: false,
+                    mode: None,
+                    rate: None,
+                    position: None,
+                    primary: false,
+                }
+            }
+            "make primary" => {
+                args.push("--auto".to_string());
+                args.push("--primary".to_string());
+                OutputConfig {
+                    name: selected.clone(),
+                    enabled: true,
+                    mode: None,
+                    rate: None,
+                    position: None,
+                    primary: true,
+                }
+            }
             _ => unreachable!(),
         };
 
-        // Build and run the xrandr command
-        let args = vec![
-            "--output".to_string(),
-            selected.clone(),
-            "--auto".to_string(),
-            format!("--{}", pos_arg),
-            primary.clone(),
-        ];
-
         println!("Running: xrandr {:?}", args);
 
         let status = Command::new("xrandr")
@@ -100,106 +544,451 @@ fn main() {
             .expect("Failed to execute xrandr command");
 
         if status.success() {
-            println!("Display attached successfully.");
-            // Remove from list to avoid re-attaching
+            println!("Action applied successfully.");
+            arrangement.push(record);
+            // Remove from list to avoid re-selecting the same output
             connected_outputs.retain(|x| x != &selected);
         } else {
-            println!("Failed to attach display. Check xrandr output for errors.");
+            println!("Failed to apply action. Check xrandr output for errors.");
+        }
+    }
+
+    // Offer to persist the arrangement: `--save NAME` supplies the name up
+    // front, otherwise prompt for one (blank skips saving).
+    if arrangement.len() > 1 {
+        let name = match save_as {
+            Some(name) => Some(name),
+            None => prompt_profile_name(),
+        };
+        if let Some(name) = name {
+            save_profile(&name, arrangement);
         }
     }
 }
 
+/// Prompt on stdin for a profile name to save the current layout under,
+/// returning `None` when the user enters a blank line.
+fn prompt_profile_name() -> Option<String> {
+    print!("Save this layout? Enter a profile name (blank to skip): ");
+    stdout().flush().expect("Failed to flush");
+
+    let mut line = String::new();
+    if stdin().read_line(&mut line).expect("Failed to read line") == 0 {
+        return None;
+    }
+    let name = line.trim();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+/// Terminals that cannot handle raw mode; we fall back to numbered prompts for
+/// these just as we do for non-TTY stdin/stdout.
+const UNSUPPORTED_TERMS: &[&str] = &["dumb", "cons25", "emacs"];
+
+/// Whether the current environment can drive the raw-mode arrow-key menu.
+///
+/// Piped input, a redirected stdout, or a `TERM` known not to support raw mode
+/// all force the numbered-prompt fallback so `xrpick` stays usable from scripts
+/// and over constrained terminals.
+fn supports_raw_mode() -> bool {
+    if !stdin().is_terminal() || !stdout().is_terminal() {
+        return false;
+    }
+    match std::env::var("TERM") {
+        Ok(term) => !UNSUPPORTED_TERMS.contains(&term.as_str()),
+        Err(_) => true,
+    }
+}
+
+/// Prompt for a resolution and refresh rate from `modes`, returning the xrandr
+/// arguments that select them (`--mode WxH --rate R`).
+///
+/// When no modes were parsed for the output we return `--auto` so attaching
+/// still works; `None` means the user quit out of one of the menus.
+fn select_mode_args(modes: &[Mode]) -> Option<Vec<String>> {
+    if modes.is_empty() {
+        return Some(vec!["--auto".to_string()]);
+    }
+
+    let resolutions: Vec<String> = modes.iter().map(Mode::resolution).collect();
+    let res_opts: Vec<&str> = resolutions.iter().map(String::as_str).collect();
+    let chosen_res = select_option(
+        "Select resolution (type to filter, arrows to move, enter to select, Esc to quit):",
+        &res_opts,
+    )?;
+
+    let mode = modes
+        .iter()
+        .find(|m| m.resolution() == chosen_res)
+        .expect("selected resolution must come from the mode list");
+
+    let rate_opts: Vec<&str> = mode.rates.iter().map(|r| r.value.as_str()).collect();
+    let chosen_rate = select_option(
+        "Select refresh rate (type to filter, arrows to move, enter to select, Esc to quit):",
+        &rate_opts,
+    )?;
+
+    Some(vec![
+        "--mode".to_string(),
+        chosen_res,
+        "--rate".to_string(),
+        chosen_rate,
+    ])
+}
+
 /// Function to handle arrow key selection
 fn select_option(title: &str, options: &[&str]) -> Option<String> {
     if options.is_empty() {
         return None;
     }
 
+    if !supports_raw_mode() {
+        return select_option_plain(title, options);
+    }
+
     enable_raw_mode().expect("Failed to enable raw mode");
-    let mut stdout = stdout();
+    let mut guard = TermGuard(stdout());
+    let stdout = &mut guard.0;
     execute!(stdout, Hide).expect("Failed to hide cursor");
 
     // Save the starting cursor position before drawing
     let start_pos = position().expect("Failed to get cursor position");
 
-    let num_lines = 1 + options.len() as u16;
-
+    let mut query = String::new();
+    let mut filtered: Vec<&str> = filter_options(options, &query);
     let mut selected_index: usize = 0;
+    // Terminal height, re-queried on every resize so the viewport tracks it.
+    let mut term_rows = size().map(|(_, rows)| rows).unwrap_or(24);
 
     // Initial clear and draw
-    clear_area(&mut stdout, start_pos, num_lines);
-    draw_menu(&mut stdout, start_pos, title, options, selected_index);
+    clear_area(stdout, start_pos, term_rows);
+    draw_menu(stdout, start_pos, title, &query, &filtered, selected_index, term_rows);
 
     loop {
-        // Read key event
-        if let Event::Key(KeyEvent { code, modifiers, .. }) = read().expect("Failed to read event") {
-            match code {
+        // Read event
+        match read().expect("Failed to read event") {
+            Event::Resize(_, rows) => {
+                term_rows = rows;
+            }
+            Event::Key(KeyEvent { code, modifiers, .. }) => match code {
                 KeyCode::Up => {
                     if selected_index > 0 {
                         selected_index -= 1;
                     }
                 }
                 KeyCode::Down => {
-                    if selected_index < options.len() - 1 {
+                    if selected_index + 1 < filtered.len() {
                         selected_index += 1;
                     }
                 }
-                KeyCode::Enter => {
-                    break;
-                }
-                KeyCode::Char('q') if modifiers.is_empty() => {
-                    clear_area(&mut stdout, start_pos, num_lines);
-                    cleanup(&mut stdout);
-                    return None;
+                KeyCode::Enter if !filtered.is_empty() => break,
+                KeyCode::Backspace => {
+                    query.pop();
+                    filtered = filter_options(options, &query);
+                    selected_index = selected_index.min(filtered.len().saturating_sub(1));
                 }
                 KeyCode::Esc => {
-                    clear_area(&mut stdout, start_pos, num_lines);
-                    cleanup(&mut stdout);
-                    return None;
+                    // Esc first clears an active filter; a second Esc quits.
+                    if query.is_empty() {
+                        clear_area(stdout, start_pos, term_rows);
+                        return None;
+                    }
+                    query.clear();
+                    filtered = filter_options(options, &query);
+                    selected_index = 0;
+                }
+                KeyCode::Char(c) if modifiers.is_empty() || modifiers == KeyModifiers::SHIFT => {
+                    query.push(c);
+                    filtered = filter_options(options, &query);
+                    selected_index = selected_index.min(filtered.len().saturating_sub(1));
                 }
                 _ => {}
-            }
+            },
+            _ => {}
         }
 
         // Redraw: Clear area, then draw
-        clear_area(&mut stdout, start_pos, num_lines);
-        draw_menu(&mut stdout, start_pos, title, options, selected_index);
+        clear_area(stdout, start_pos, term_rows);
+        draw_menu(stdout, start_pos, title, &query, &filtered, selected_index, term_rows);
     }
 
     // After enter: Clear the menu to remove it after choice
-    clear_area(&mut stdout, start_pos, num_lines);
+    clear_area(stdout, start_pos, term_rows);
 
-    cleanup(&mut stdout);
+    Some(filtered[selected_index].to_string())
+}
 
-    Some(options[selected_index].to_string())
+/// Case-insensitive substring filter over `options`, preserving their order.
+/// An empty query matches everything.
+fn filter_options<'a>(options: &[&'a str], query: &str) -> Vec<&'a str> {
+    if query.is_empty() {
+        return options.to_vec();
+    }
+    let needle = query.to_lowercase();
+    options
+        .iter()
+        .filter(|opt| opt.to_lowercase().contains(&needle))
+        .copied()
+        .collect()
 }
 
-fn draw_menu(stdout: &mut Stdout, start_pos: (u16, u16), title: &str, options: &[&str], selected_index: usize) {
+/// Numbered-prompt fallback used when raw mode is unavailable.
+///
+/// Prints the options as a plain `1) ...` list and reads a line from stdin,
+/// re-prompting on anything that isn't a valid index. An empty line or `q`
+/// quits, as Esc does in the interactive menu.
+fn select_option_plain(title: &str, options: &[&str]) -> Option<String> {
+    let mut stdout = stdout();
+    loop {
+        println!("{}", title);
+        for (i, opt) in options.iter().enumerate() {
+            println!("  {}) {}", i + 1, opt);
+        }
+        print!("Enter a number (1-{}, q to quit): ", options.len());
+        stdout.flush().expect("Failed to flush");
+
+        let mut line = String::new();
+        if stdin().read_line(&mut line).expect("Failed to read line") == 0 {
+            // EOF on a pipe: treat like quit.
+            return None;
+        }
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.eq_ignore_ascii_case("q") {
+            return None;
+        }
+
+        match trimmed.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= options.len() => return Some(options[n - 1].to_string()),
+            _ => println!("Invalid selection: {:?}", trimmed),
+        }
+    }
+}
+
+fn draw_menu(stdout: &mut Stdout, start_pos: (u16, u16), title: &str, query: &str, options: &[&str], selected_index: usize, term_rows: u16) {
     let mut current_row = start_pos.1;
 
-    // Print title
-    execute!(stdout, MoveTo(0, current_row), Print(title)).expect("Failed to print title");
+    // Print title, echoing the current filter query so the user sees what they
+    // typed; an empty query leaves the title line unchanged.
+    if query.is_empty() {
+        execute!(stdout, MoveTo(0, current_row), Print(title)).expect("Failed to print title");
+    } else {
+        execute!(stdout, MoveTo(0, current_row), Print(format!("{} {}", title, query)))
+            .expect("Failed to print title");
+    }
     current_row += 1;
 
-    // Print options
-    for (i, opt) in options.iter().enumerate() {
-        let prefix = if i == selected_index { "> " } else { "  " };
-        execute!(stdout, MoveTo(0, current_row), Print(prefix), Print(opt)).expect("Failed to print option");
-        current_row += 1;
+    // Rows available below the title for the option list.
+    let avail = term_rows.saturating_sub(current_row) as usize;
+
+    if options.len() <= avail {
+        // Everything fits: draw the whole list.
+        for (i, opt) in options.iter().enumerate() {
+            draw_row(stdout, &mut current_row, i == selected_index, opt);
+        }
+    } else {
+        // Reserve the top and bottom rows for scroll indicators and show a
+        // window of entries around the selection. On a terminal too short to
+        // fit both indicators plus an entry, drop the indicators so the list
+        // never overruns the bottom of the screen.
+        let show_indicators = avail >= 3;
+        let indicator_rows = if show_indicators { 2 } else { 0 };
+        let capacity = avail.saturating_sub(indicator_rows).max(1);
+        let (start, end) = viewport(options.len(), selected_index, capacity);
+
+        if show_indicators {
+            let top = if start > 0 { "  \u{25b2}" } else { "" };
+            execute!(stdout, MoveTo(0, current_row), Print(top)).expect("Failed to print indicator");
+            current_row += 1;
+        }
+
+        for (offset, opt) in options[start..end].iter().enumerate() {
+            draw_row(stdout, &mut current_row, start + offset == selected_index, opt);
+        }
+
+        if show_indicators {
+            let bottom = if end < options.len() { "  \u{25bc}" } else { "" };
+            execute!(stdout, MoveTo(0, current_row), Print(bottom)).expect("Failed to print indicator");
+        }
     }
 
     stdout.flush().expect("Failed to flush");
 }
 
-fn clear_area(stdout: &mut Stdout, start_pos: (u16, u16), num_lines: u16) {
-    for i in 0..num_lines {
-        execute!(stdout, MoveTo(0, start_pos.1 + i), Clear(ClearType::CurrentLine)).expect("Failed to clear line");
+/// Print one option row with the selection prefix and advance `row`.
+fn draw_row(stdout: &mut Stdout, row: &mut u16, selected: bool, opt: &str) {
+    let prefix = if selected { "> " } else { "  " };
+    execute!(stdout, MoveTo(0, *row), Print(prefix), Print(opt)).expect("Failed to print option");
+    *row += 1;
+}
+
+/// The half-open slice of options to display so `selected` stays visible within
+/// `capacity` rows, clamped to the ends of the list.
+fn viewport(len: usize, selected: usize, capacity: usize) -> (usize, usize) {
+    if len <= capacity {
+        return (0, len);
+    }
+    let start = selected
+        .saturating_sub(capacity / 2)
+        .min(len - capacity);
+    (start, start + capacity)
+}
+
+fn clear_area(stdout: &mut Stdout, start_pos: (u16, u16), term_rows: u16) {
+    // Clear every row from the menu's start down to the bottom of the screen so
+    // a shrunk filter result or a resize never leaves stale lines behind.
+    for row in start_pos.1..term_rows {
+        execute!(stdout, MoveTo(0, row), Clear(ClearType::CurrentLine)).expect("Failed to clear line");
     }
     execute!(stdout, MoveTo(0, start_pos.1)).expect("Failed to reset cursor");
     stdout.flush().expect("Failed to flush");
 }
 
-fn cleanup(stdout: &mut Stdout) {
-    execute!(stdout, Show).expect("Failed to show cursor");
-    disable_raw_mode().expect("Failed to disable raw mode");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_modes_reads_resolutions_and_flags() {
+        let query = "\
+eDP-1 connected primary 1920x1080+0+0 (normal left inverted right)
+   1920x1080     60.00*+  59.93
+   1280x720      60.00
+HDMI-1 disconnected
+DP-1 connected
+   2560x1440     59.95+
+";
+        let modes = parse_modes(query);
+
+        let edp = modes.get("eDP-1").expect("eDP-1 modes");
+        assert_eq!(edp.len(), 2);
+        assert_eq!(edp[0].resolution(), "1920x1080");
+        assert_eq!(edp[0].rates[0].value, "60.00");
+        assert!(edp[0].rates[0].current);
+        assert!(edp[0].rates[0].preferred);
+        assert!(!edp[0].rates[1].current);
+
+        // Disconnected outputs contribute no modes.
+        assert!(!modes.contains_key("HDMI-1"));
+        assert_eq!(modes.get("DP-1").map(Vec::len), Some(1));
+    }
+
+    #[test]
+    fn parse_modes_skips_interlaced_lines() {
+        // xrandr appends an `i` to interlaced modes (e.g. `1920x1080i`); the
+        // height fails to parse as a plain integer and the line is dropped.
+        let query = "\
+HDMI-1 connected
+   1920x1080i    60.00
+   1920x1080     60.00*
+";
+        let modes = parse_modes(query);
+        let hdmi = modes.get("HDMI-1").expect("HDMI-1 modes");
+        assert_eq!(hdmi.len(), 1);
+        assert_eq!(hdmi[0].resolution(), "1920x1080");
+    }
+
+    #[test]
+    fn common_mode_prefers_shared_preferred_resolution() {
+        let primary = vec![
+            Mode { width: 1920, height: 1080, rates: vec![] },
+            Mode { width: 1280, height: 720, rates: vec![] },
+        ];
+        let output = vec![
+            Mode { width: 2560, height: 1440, rates: vec![] },
+            Mode {
+                width: 1280,
+                height: 720,
+                rates: vec![Rate { value: "60.00".into(), current: false, preferred: true }],
+            },
+            Mode { width: 1920, height: 1080, rates: vec![] },
+        ];
+        assert_eq!(common_mode(&primary, &output), Some("1280x720".to_string()));
+    }
+
+    #[test]
+    fn common_mode_none_without_overlap() {
+        let primary = vec![Mode { width: 1920, height: 1080, rates: vec![] }];
+        let output = vec![Mode { width: 2560, height: 1440, rates: vec![] }];
+        assert_eq!(common_mode(&primary, &output), None);
+    }
+
+    #[test]
+    fn viewport_clamps_to_ends() {
+        // Window stays within the list and keeps the selection visible.
+        assert_eq!(viewport(10, 0, 4), (0, 4));
+        assert_eq!(viewport(10, 5, 4), (3, 7));
+        assert_eq!(viewport(10, 9, 4), (6, 10));
+        // Everything fits.
+        assert_eq!(viewport(3, 2, 5), (0, 3));
+    }
+
+    #[test]
+    fn profiles_round_trip() {
+        let mut profiles: BTreeMap<String, Vec<OutputConfig>> = BTreeMap::new();
+        profiles.insert(
+            "dock".to_string(),
+            vec![
+                OutputConfig {
+                    name: "eDP-1".into(),
+                    enabled: true,
+                    mode: Some("1920x1080".into()),
+                    rate: Some("60.00".into()),
+                    position: None,
+                    primary: true,
+                },
+                OutputConfig {
+                    name: "HDMI-1".into(),
+                    enabled: true,
+                    mode: Some("2560x1440".into()),
+                    rate: None,
+                    position: Some(("right-of".into(), "eDP-1".into())),
+                    primary: false,
+                },
+                OutputConfig {
+                    name: "DP-1".into(),
+                    enabled: false,
+                    mode: None,
+                    rate: None,
+                    position: None,
+                    primary: false,
+                },
+            ],
+        );
+
+        let parsed = parse_profiles(&serialize_profiles(&profiles));
+        let dock = parsed.get("dock").expect("dock profile");
+        assert_eq!(dock.len(), 3);
+        assert_eq!(dock[0].name, "eDP-1");
+        assert!(dock[0].primary);
+        assert_eq!(dock[0].mode.as_deref(), Some("1920x1080"));
+        assert_eq!(dock[0].rate.as_deref(), Some("60.00"));
+        assert_eq!(dock[1].position, Some(("right-of".to_string(), "eDP-1".to_string())));
+        assert!(!dock[2].enabled);
+    }
+
+    #[test]
+    fn output_config_to_args() {
+        let cfg = OutputConfig {
+            name: "HDMI-1".into(),
+            enabled: true,
+            mode: Some("1920x1080".into()),
+            rate: Some("60.00".into()),
+            position: Some(("right-of".into(), "eDP-1".into())),
+            primary: false,
+        };
+        assert_eq!(
+            cfg.to_args(),
+            [
+                "--output", "HDMI-1", "--mode", "1920x1080", "--rate", "60.00",
+                "--right-of", "eDP-1",
+            ]
+        );
+
+        let off = OutputConfig { name: "DP-1".into(), ..Default::default() };
+        assert_eq!(off.to_args(), ["--output", "DP-1", "--off"]);
+    }
 }
\ No newline at end of file